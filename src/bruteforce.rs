@@ -0,0 +1,188 @@
+use std::{
+    sync::{
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread::{sleep, spawn},
+    time::{Duration, Instant},
+};
+
+use tracing::info;
+
+use crate::{search::Search, ssh::KeyPair};
+
+/// Shared progress counters for a single search, whether it is running
+/// entirely locally or as one worker among many.
+pub struct State {
+    pub counter: u64,
+    start: Instant,
+    iteration: Instant,
+    /// A rough `1/p` estimate of how many attempts a match should take,
+    /// logged alongside the counter as an ETA. `None` when no closed-form
+    /// probability applies (regex mode).
+    expected_attempts: Option<f64>,
+}
+
+impl State {
+    pub fn new(expected_attempts: Option<f64>) -> Self {
+        State {
+            counter: 0,
+            start: Instant::now(),
+            iteration: Instant::now(),
+            expected_attempts,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    pub fn print_details(&mut self) {
+        let total_duration = Instant::now().duration_since(self.start);
+        let iteration_duration = Instant::now().duration_since(self.iteration);
+        self.iteration = Instant::now();
+
+        let Some(expected) = self.expected_attempts else {
+            info!(
+                "{:#?} total (last {:#?}); {} attempts",
+                total_duration, iteration_duration, self.counter
+            );
+            return;
+        };
+
+        let rate = self.counter as f64 / total_duration.as_secs_f64().max(f64::EPSILON);
+        let remaining = (expected - self.counter as f64).max(0.0);
+        let eta = if rate > 0.0 {
+            Duration::from_secs_f64(remaining / rate)
+        } else {
+            Duration::MAX
+        };
+
+        info!(
+            "{:#?} total (last {:#?}); {} attempts (~{:.0} expected, eta {:#?})",
+            total_duration, iteration_duration, self.counter, expected, eta
+        );
+    }
+}
+
+/// A found keypair, handed back from a worker thread over the `done`
+/// channel. Everything lives in memory until this point; nothing is
+/// written to disk unless a match is found.
+pub struct Found {
+    pub private_pem: String,
+    pub public_line: String,
+}
+
+/// The hot loop: generate a key, check it against `search`, repeat until
+/// either a match is found or `finished` is flipped by someone else (a
+/// sibling thread, a budget guard, a Ctrl-C, or a `Stop` message from a
+/// coordinator).
+pub fn guess(
+    comment: Arc<str>,
+    search: Arc<Search>,
+    print_every: u64,
+    state: Arc<Mutex<State>>,
+    finished: Arc<RwLock<bool>>,
+    done: Sender<Found>,
+) {
+    loop {
+        if *finished.read().expect("could not read finished state") {
+            return;
+        }
+
+        let key_pair = KeyPair::generate();
+        let word = key_pair.public_key_base64();
+
+        if search.is_match(&word) {
+            let found = Found {
+                private_pem: key_pair.private_key_pem(&comment),
+                public_line: key_pair.public_key_line(&comment),
+            };
+            let _ = done.send(found);
+            *finished.write().expect("could not write to finished state") = true;
+            return;
+        }
+
+        let mut s = state.lock().expect("could not get state");
+        s.counter += 1;
+        if s.counter % print_every == 0 {
+            s.print_details();
+        }
+    }
+}
+
+/// Attempt/time guards that stop a run early instead of letting it spin
+/// forever once it has burned through a user-set budget.
+#[derive(Clone, Copy, Default)]
+pub struct Budget {
+    pub max_attempts: Option<u64>,
+    pub max_duration: Option<Duration>,
+}
+
+impl Budget {
+    pub fn is_unbounded(&self) -> bool {
+        self.max_attempts.is_none() && self.max_duration.is_none()
+    }
+}
+
+/// Polls `state` against `budget` and flips `finished` the moment either
+/// guard is exceeded, so a run stops cleanly instead of spinning forever.
+pub fn spawn_budget_guard(budget: Budget, state: Arc<Mutex<State>>, finished: Arc<RwLock<bool>>) {
+    if budget.is_unbounded() {
+        return;
+    }
+
+    spawn(move || loop {
+        if *finished.read().expect("could not read finished state") {
+            return;
+        }
+
+        let (counter, elapsed) = {
+            let s = state.lock().expect("could not get state");
+            (s.counter, s.elapsed())
+        };
+
+        let attempts_exceeded = budget.max_attempts.is_some_and(|max| counter >= max);
+        let duration_exceeded = budget.max_duration.is_some_and(|max| elapsed >= max);
+
+        if attempts_exceeded || duration_exceeded {
+            info!(
+                "budget exhausted ({} attempts, {:#?} elapsed), stopping",
+                counter, elapsed
+            );
+            *finished.write().expect("could not write finished state") = true;
+            return;
+        }
+
+        sleep(Duration::from_millis(250));
+    });
+}
+
+/// Installs a Ctrl-C handler that flips `finished` so every worker thread
+/// and the main wait loop unwind on their own instead of the process being
+/// killed mid-run.
+pub fn install_ctrlc_handler(finished: Arc<RwLock<bool>>) {
+    let result = ctrlc::set_handler(move || {
+        info!("ctrl-c received, stopping...");
+        *finished.write().expect("could not write finished state") = true;
+    });
+    if let Err(err) = result {
+        info!("could not install ctrl-c handler: {}", err);
+    }
+}
+
+/// Blocks until either a worker reports a `Found`, or `finished` is
+/// flipped (by a budget guard or Ctrl-C) with nothing to show for it.
+pub fn wait_for_result(receiver: &Receiver<Found>, finished: &Arc<RwLock<bool>>) -> Option<Found> {
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(250)) {
+            Ok(found) => return Some(found),
+            Err(RecvTimeoutError::Disconnected) => return None,
+            Err(RecvTimeoutError::Timeout) => {
+                if *finished.read().expect("could not read finished state") {
+                    return receiver.try_recv().ok();
+                }
+            }
+        }
+    }
+}
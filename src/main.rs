@@ -1,32 +1,44 @@
 use std::{
-    fs::{self},
-    process::{self, Command, Stdio},
-    sync::{
-        mpsc::{channel, Sender},
-        Arc, Mutex, RwLock,
-    },
-    thread::{sleep, spawn},
-    time::{Duration, Instant},
+    fs, process,
+    sync::{mpsc::channel, Arc, Mutex, RwLock},
+    thread::spawn,
+    time::Duration,
 };
 
 use anyhow::Result;
 use clap::Parser;
-use tempfile::{tempdir_in, TempDir};
 use tracing::{error, info};
 
+mod bruteforce;
+mod matcher;
+mod net;
+mod search;
+mod ssh;
+mod stats;
+use bruteforce::{guess, install_ctrlc_handler, spawn_budget_guard, Budget, Found, State};
+use search::{MatchMode, Search};
+use ssh::PUBLIC_KEY_BASE64_LEN;
+
 /// Program used to brute force a SSH public key with certain contents
 #[derive(Parser, Debug)]
 #[command(author = "KuNet", version = env!("CARGO_PKG_VERSION"))]
 struct Args {
-    /// Comment (in most cases your email address)
-    #[arg(short = 'C', long)]
+    /// Comment (in most cases your email address). Ignored in `--connect`
+    /// mode, where the coordinator provides it.
+    #[arg(short = 'C', long, default_value = "")]
     comment: String,
 
-    /// What to search for separated by commas
-    #[arg(short, long)]
+    /// What to search for separated by commas. Ignored in `--connect`
+    /// mode, where the coordinator provides it.
+    #[arg(short, long, default_value = "")]
     search: String,
 
-    /// Key type
+    /// Where a search term must land in the key body. Ignored in
+    /// `--connect` mode, where the coordinator provides it.
+    #[arg(long, value_enum, default_value = "contains")]
+    r#match: MatchMode,
+
+    /// Key type (only ed25519 is generated natively)
     #[arg(
         short = 't',
         long = "type",
@@ -42,173 +54,121 @@ struct Args {
     /// Output name
     #[arg(short, long, default_value = "bruted")]
     output: String,
-}
 
-struct State {
-    counter: u64,
-    start: Instant,
-    iteration: Instant,
-}
+    /// Give up after this many attempts instead of running forever
+    #[arg(long, value_name = "N")]
+    max_attempts: Option<u64>,
 
-impl State {
-    fn print_details(&mut self) {
-        let total_duration = Instant::now().duration_since(self.start);
-        let iteration_duration = Instant::now().duration_since(self.iteration);
-        self.iteration = Instant::now();
+    /// Give up after this many seconds instead of running forever
+    #[arg(long, value_name = "SECONDS")]
+    max_duration: Option<u64>,
 
-        info!(
-            "{:#?} total (last {:#?}); {} attempts",
-            total_duration, iteration_duration, self.counter
-        );
-    }
-}
+    /// Run as the coordinator of a distributed search, binding this
+    /// address (e.g. `0.0.0.0:9000`) for workers to connect to
+    #[arg(long, value_name = "ADDR", conflicts_with = "connect")]
+    listen: Option<String>,
 
-fn guess(
-    args: Arc<Args>,
-    search_terms: Arc<Vec<String>>,
-    path: Arc<TempDir>,
-    state: Arc<Mutex<State>>,
-    finished: Arc<RwLock<bool>>,
-    done: Sender<usize>,
-    number: usize,
-) {
-    let pub_path = path.path().join(number.to_string() + ".pub");
-    let private_path = path.path().join(number.to_string());
-
-    loop {
-        if *finished.read().expect("could not read finished state") {
-            return;
-        }
-
-        let mut command = Command::new("ssh-keygen");
-        command.current_dir(path.path());
-        command.stdout(Stdio::null());
-        command.stderr(Stdio::null());
-        command.arg("-t");
-        command.arg(&args.key_type);
-        command.arg("-C");
-        command.arg(&args.comment);
-        command.arg("-f");
-        command.arg(number.to_string());
-        command.arg("-N");
-        command.arg("\"\"");
-
-        command.status().expect("generating key failed");
-
-        let content = fs::read_to_string(pub_path.clone()).expect("could not read key pub data");
-        let split: Vec<&str> = content.split(' ').collect();
-        if split.len() != 3 {
-            panic!("key does not have 3 parts how");
-        }
-
-        let word = split
-            .get(1)
-            .expect("this can't happen")
-            .to_ascii_lowercase();
-
-        for term in search_terms.iter() {
-            if word.contains(term) {
-                let _ = done.send(number);
-                *finished.write().expect("could not write to finished state") = true;
-                return;
-            }
-        }
-
-        fs::remove_file(pub_path.clone()).expect("could not delete public key");
-        fs::remove_file(private_path.clone()).expect("could not delete private key");
-
-        {
-            let mut s = state.lock().expect("could not get state");
-            s.counter += 1;
-            if s.counter % args.print_every == 0 {
-                s.print_details();
-            }
-        }
-    }
+    /// Run as a worker, connecting to a coordinator at this address
+    /// instead of searching locally
+    #[arg(long, value_name = "ADDR", conflicts_with = "listen")]
+    connect: Option<String>,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    let args_arc = Arc::new(args);
     tracing_subscriber::fmt::init();
 
-    let split = args_arc.search.split(',');
-    if split.clone().count() == 0 {
+    if args.key_type != "ed25519" {
+        error!("only ed25519 keys are generated natively, try --type ed25519");
+        process::exit(1);
+    }
+
+    let threads = num_cpus::get();
+    let budget = Budget {
+        max_attempts: args.max_attempts,
+        max_duration: args.max_duration.map(Duration::from_secs),
+    };
+
+    if let Some(connect) = &args.connect {
+        info!("starting {} threads", threads);
+        return net::run_worker(connect, threads, args.print_every, budget);
+    }
+
+    let split = args.search.split(',');
+    if args.connect.is_none() && split.clone().any(|term| term.trim().is_empty()) {
         error!("search for something! try something like \"-s real,word,search\"");
         process::exit(1);
     }
 
-    if split
-        .clone()
-        .any(|item| !item.is_ascii() && item.chars().any(|c| !char::is_alphanumeric(c)))
+    if args.r#match != MatchMode::Regex
+        && split
+            .clone()
+            .any(|item| !item.is_ascii() || item.chars().any(|c| !char::is_alphanumeric(c)))
     {
         error!("make sure your search terms are alphanumeric");
         process::exit(1);
     }
 
-    let search_terms: Vec<String> = split.map(|s| s.to_ascii_lowercase()).collect();
-    let search_terms_arc = Arc::new(search_terms);
-
-    info!("searching for:");
-    for search_term in search_terms_arc.iter() {
+    // Regex terms keep their original case (patterns may rely on it, e.g.
+    // `[A-Z]{3}`); every other mode is matched case-folded, so its terms are
+    // lowercased up front.
+    let search_terms: Vec<String> = if args.r#match == MatchMode::Regex {
+        split.map(str::to_owned).collect()
+    } else {
+        split.map(|s| s.to_ascii_lowercase()).collect()
+    };
+
+    info!("searching for ({:?}):", args.r#match);
+    for search_term in &search_terms {
         info!(" - {}", search_term);
     }
 
-    let temp = tempdir_in(".")?;
-    info!(
-        "created temporary directory {:?} (delete me if you cancel!)",
-        temp.path().file_name().expect("temp directory has no name")
-    );
+    if let Some(listen) = &args.listen {
+        return net::run_coordinator(
+            listen,
+            args.comment,
+            search_terms,
+            args.r#match,
+            &args.output,
+            budget,
+        );
+    }
 
-    let path_arc = Arc::new(temp);
+    let comment: Arc<str> = Arc::from(args.comment.as_str());
+    let search_arc = Arc::new(Search::new(args.r#match, &search_terms)?);
+    let expected = stats::expected_attempts(&search_terms, args.r#match, PUBLIC_KEY_BASE64_LEN);
 
-    let threads = num_cpus::get();
     info!("starting {} threads", threads);
 
-    let state = Arc::new(Mutex::new(State {
-        counter: 0,
-        start: Instant::now(),
-        iteration: Instant::now(),
-    }));
-
-    let (sender, receiver) = channel::<usize>();
+    let state = Arc::new(Mutex::new(State::new(expected)));
+    let (sender, receiver) = channel::<Found>();
     let finished = Arc::new(RwLock::new(false));
 
-    for n in 0..threads {
-        let args_clone = args_arc.clone();
-        let search_terms_clone = search_terms_arc.clone();
-        let path_clone = path_arc.clone();
+    install_ctrlc_handler(finished.clone());
+    spawn_budget_guard(budget, state.clone(), finished.clone());
+
+    for _ in 0..threads {
+        let comment = comment.clone();
+        let search_clone = search_arc.clone();
         let state_clone = state.clone();
         let finished_clone = finished.clone();
         let sender_clone = sender.clone();
+        let print_every = args.print_every;
 
-        spawn(move || {
-            guess(
-                args_clone,
-                search_terms_clone,
-                path_clone,
-                state_clone,
-                finished_clone,
-                sender_clone,
-                n,
-            )
-        });
+        spawn(move || guess(comment, search_clone, print_every, state_clone, finished_clone, sender_clone));
     }
 
-    let worker = receiver.recv().unwrap();
+    let Some(found) = bruteforce::wait_for_result(&receiver, &finished) else {
+        info!("stopping without a match");
+        return Ok(());
+    };
+
     state.lock().unwrap().print_details();
     info!("found!");
 
-    let pub_path = path_arc.path().join(worker.to_string() + ".pub");
-    let private_path = path_arc.path().join(worker.to_string());
-
-    // just in case copies break FOR SOME REASON copy the private FIRST
-    fs::copy(private_path, &args_arc.output)?;
-    fs::copy(pub_path, args_arc.output.to_owned() + ".pub")?;
-
-    // ok yeah I know this is really bad memory management
-    // I should have just written this in Zig
-    sleep(Duration::from_secs(1));
+    // just in case writes break FOR SOME REASON write the private key FIRST
+    fs::write(&args.output, found.private_pem)?;
+    fs::write(args.output.to_owned() + ".pub", found.public_line)?;
 
     Ok(())
 }
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+use anyhow::{bail, Result};
+
+/// Every byte that can appear in a base64-encoded key body (or a lowercased
+/// search term) is ASCII, so transitions are indexed directly by byte value
+/// instead of reaching for a hash map on every step.
+const ALPHABET: usize = 128;
+
+struct Node {
+    /// Goto transitions. Trie edges are filled in during insertion; every
+    /// other entry is backfilled during the BFS pass below so that scanning
+    /// never has to fall back to a fail link at match time.
+    goto: [usize; ALPHABET],
+    fail: usize,
+    terminal: bool,
+}
+
+impl Node {
+    fn new() -> Self {
+        Node {
+            goto: [0; ALPHABET],
+            fail: 0,
+            terminal: false,
+        }
+    }
+}
+
+/// An Aho–Corasick automaton over a fixed set of patterns, used to check in
+/// one linear pass whether any of `search_terms` occurs in a generated key.
+pub struct Matcher {
+    nodes: Vec<Node>,
+}
+
+impl Matcher {
+    /// Builds the automaton once from the (already lowercased) search terms.
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut nodes = vec![Node::new()];
+        let mut has_trie_edge = vec![[false; ALPHABET]];
+
+        for pattern in patterns {
+            if !pattern.is_ascii() {
+                bail!(
+                    "search term {pattern:?} contains non-ASCII characters, which can never appear in a base64 key body"
+                );
+            }
+
+            let mut state = 0;
+            for &byte in pattern.as_bytes() {
+                let byte = byte as usize;
+                if !has_trie_edge[state][byte] {
+                    nodes.push(Node::new());
+                    has_trie_edge.push([false; ALPHABET]);
+                    let new_state = nodes.len() - 1;
+                    nodes[state].goto[byte] = new_state;
+                    has_trie_edge[state][byte] = true;
+                }
+                state = nodes[state].goto[byte];
+            }
+            nodes[state].terminal = true;
+        }
+
+        // BFS over the trie to compute fail links (root's children fail to
+        // root, everyone else fails to the longest proper suffix that is
+        // also a trie prefix) while filling in goto transitions for bytes
+        // that have no trie edge, so the scan loop is a pure table lookup.
+        let mut queue = VecDeque::new();
+        for byte in 0..ALPHABET {
+            if has_trie_edge[0][byte] {
+                let child = nodes[0].goto[byte];
+                nodes[child].fail = 0;
+                queue.push_back(child);
+            }
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let fail = nodes[state].fail;
+            for byte in 0..ALPHABET {
+                if has_trie_edge[state][byte] {
+                    let child = nodes[state].goto[byte];
+                    nodes[child].fail = nodes[fail].goto[byte];
+                    nodes[child].terminal |= nodes[nodes[child].fail].terminal;
+                    queue.push_back(child);
+                } else {
+                    nodes[state].goto[byte] = nodes[fail].goto[byte];
+                }
+            }
+        }
+
+        Ok(Matcher { nodes })
+    }
+
+    /// Scans `text` once and reports whether any pattern occurs in it.
+    pub fn is_match(&self, text: &str) -> bool {
+        let mut state = 0;
+        for &byte in text.as_bytes() {
+            let byte = byte as usize;
+            if byte >= ALPHABET {
+                continue;
+            }
+            state = self.nodes[state].goto[byte];
+            if self.nodes[state].terminal {
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::channel,
+        Arc, Mutex, RwLock,
+    },
+    thread::{sleep, spawn},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use tracing::{error, info};
+
+use crate::{
+    bruteforce::{
+        guess, install_ctrlc_handler, spawn_budget_guard, wait_for_result, Budget, Found, State,
+    },
+    search::{MatchMode, Search},
+    ssh::PUBLIC_KEY_BASE64_LEN,
+    stats,
+};
+
+/// Wire protocol between a coordinator and its workers. Framed on the wire
+/// as a big-endian `u32` length followed by the bincode-encoded message.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub enum Message {
+    /// Coordinator -> worker: the job to run.
+    Assign {
+        comment: String,
+        search_terms: Vec<String>,
+        match_mode: MatchMode,
+    },
+    /// Worker -> coordinator: attempts made since the last report.
+    Progress { attempts: u64 },
+    /// Worker -> coordinator: a match was found.
+    Found {
+        private_blob: String,
+        public_blob: String,
+    },
+    /// Coordinator -> worker: stop searching. Sent when a match was found,
+    /// and also when the coordinator stops without one (budget or Ctrl-C).
+    Stop,
+}
+
+pub fn send(stream: &mut TcpStream, message: &Message) -> Result<()> {
+    let bytes = bincode::serialize(message).context("encoding message")?;
+    stream
+        .write_all(&(bytes.len() as u32).to_be_bytes())
+        .context("writing message length")?;
+    stream.write_all(&bytes).context("writing message body")?;
+    Ok(())
+}
+
+pub fn recv(stream: &mut TcpStream) -> Result<Message> {
+    let mut len_buf = [0u8; 4];
+    stream.read_exact(&mut len_buf).context("reading message length")?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    let mut buf = vec![0u8; len];
+    stream.read_exact(&mut buf).context("reading message body")?;
+    bincode::deserialize(&buf).context("decoding message")
+}
+
+/// Runs as the coordinator: binds `addr`, hands the search job to every
+/// worker that connects, aggregates their progress reports, and writes
+/// `output` the instant any worker reports `Found`.
+pub fn run_coordinator(
+    addr: &str,
+    comment: String,
+    search_terms: Vec<String>,
+    match_mode: MatchMode,
+    output: &str,
+    budget: Budget,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("binding {addr}"))?;
+    info!("coordinator listening on {}", addr);
+
+    let expected = stats::expected_attempts(&search_terms, match_mode, PUBLIC_KEY_BASE64_LEN);
+    let state = Arc::new(Mutex::new(State::new(expected)));
+    let finished = Arc::new(RwLock::new(false));
+    let workers: Arc<Mutex<Vec<TcpStream>>> = Arc::new(Mutex::new(Vec::new()));
+    let (sender, receiver) = channel::<Found>();
+
+    install_ctrlc_handler(finished.clone());
+    spawn_budget_guard(budget, state.clone(), finished.clone());
+
+    // Accept connections on their own thread so the coordinator can react
+    // to a `Found` from an already-connected worker without waiting on the
+    // next `accept()` to unblock.
+    {
+        let finished = finished.clone();
+        let workers = workers.clone();
+        let state = state.clone();
+        spawn(move || {
+            for incoming in listener.incoming() {
+                if *finished.read().expect("could not read finished state") {
+                    return;
+                }
+
+                let mut stream = match incoming {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        error!("worker connection failed: {}", err);
+                        continue;
+                    }
+                };
+
+                if send(
+                    &mut stream,
+                    &Message::Assign {
+                        comment: comment.clone(),
+                        search_terms: search_terms.clone(),
+                        match_mode,
+                    },
+                )
+                .is_err()
+                {
+                    continue;
+                }
+
+                if let Ok(peer) = stream.peer_addr() {
+                    info!("worker {} joined", peer);
+                }
+
+                let Ok(worker_copy) = stream.try_clone() else {
+                    continue;
+                };
+                workers.lock().expect("could not lock worker list").push(worker_copy);
+
+                let state = state.clone();
+                let finished = finished.clone();
+                let sender = sender.clone();
+
+                spawn(move || loop {
+                    let message = match recv(&mut stream) {
+                        Ok(message) => message,
+                        Err(_) => return,
+                    };
+
+                    match message {
+                        Message::Progress { attempts } => {
+                            let mut s = state.lock().expect("could not get state");
+                            s.counter += attempts;
+                            s.print_details();
+                        }
+                        Message::Found {
+                            private_blob,
+                            public_blob,
+                        } => {
+                            let _ = sender.send(Found {
+                                private_pem: private_blob,
+                                public_line: public_blob,
+                            });
+                            *finished.write().expect("could not write finished state") = true;
+                            return;
+                        }
+                        _ => {}
+                    }
+                });
+            }
+        });
+    }
+
+    let found = wait_for_result(&receiver, &finished);
+    *finished.write().expect("could not write finished state") = true;
+
+    info!("stopping every connected worker");
+    for mut worker in workers.lock().expect("could not lock worker list").drain(..) {
+        let _ = send(&mut worker, &Message::Stop);
+    }
+
+    let Some(found) = found else {
+        info!("stopping without a match");
+        return Ok(());
+    };
+
+    info!("found!");
+    std::fs::write(output, found.private_pem)?;
+    std::fs::write(output.to_owned() + ".pub", found.public_line)?;
+
+    Ok(())
+}
+
+/// Runs as a worker: connects to the coordinator at `addr`, receives the
+/// job, then runs the usual local thread pool while streaming progress
+/// back and watching for a `Stop` broadcast.
+pub fn run_worker(addr: &str, threads: usize, print_every: u64, budget: Budget) -> Result<()> {
+    let mut stream = TcpStream::connect(addr).with_context(|| format!("connecting to {addr}"))?;
+    info!("connected to coordinator at {}", addr);
+
+    let assign = recv(&mut stream)?;
+    let (comment, search_terms, match_mode) = match assign {
+        Message::Assign {
+            comment,
+            search_terms,
+            match_mode,
+        } => (comment, search_terms, match_mode),
+        other => anyhow::bail!("expected an Assign message, got {:?}", other),
+    };
+
+    info!("assigned search for {:?} terms (mode {:?})", search_terms, match_mode);
+    let comment: Arc<str> = Arc::from(comment.as_str());
+    let search = Arc::new(Search::new(match_mode, &search_terms)?);
+    let expected = stats::expected_attempts(&search_terms, match_mode, PUBLIC_KEY_BASE64_LEN);
+
+    let state = Arc::new(Mutex::new(State::new(expected)));
+    let finished = Arc::new(RwLock::new(false));
+    let (sender, receiver) = channel::<Found>();
+
+    install_ctrlc_handler(finished.clone());
+    spawn_budget_guard(budget, state.clone(), finished.clone());
+
+    for _ in 0..threads {
+        let comment = comment.clone();
+        let search = search.clone();
+        let state = state.clone();
+        let finished = finished.clone();
+        let sender = sender.clone();
+
+        spawn(move || guess(comment, search, print_every, state, finished, sender));
+    }
+
+    // From here on, `stream` is only ever read from (by the stop-watcher
+    // below); every outbound message goes through this mutex-guarded clone
+    // instead, so the progress reporter and the final `Found` send can
+    // never interleave their writes and corrupt the length-prefixed framing.
+    let write_stream = Arc::new(Mutex::new(
+        stream.try_clone().context("cloning coordinator stream")?,
+    ));
+
+    // Watch the coordinator for a `Stop` broadcast (another worker found a
+    // match, or the coordinator is stopping) and shut this worker's threads
+    // down too. A dropped/errored
+    // connection (the coordinator crashed, or it moved on without us) is
+    // treated the same way - there is no reconnect path, so there is
+    // nothing left to do but stop.
+    let finished_watch = finished.clone();
+    spawn(move || loop {
+        match recv(&mut stream) {
+            Ok(Message::Stop) => {
+                *finished_watch
+                    .write()
+                    .expect("could not write finished state") = true;
+                return;
+            }
+            Ok(_) => {}
+            Err(_) => {
+                *finished_watch
+                    .write()
+                    .expect("could not write finished state") = true;
+                return;
+            }
+        }
+    });
+
+    // Report attempts made so far every couple of seconds until we stop.
+    let reporter_state = state.clone();
+    let reporter_finished = finished.clone();
+    let reporter_write_stream = write_stream.clone();
+    spawn(move || {
+        let mut last = 0u64;
+        loop {
+            sleep(Duration::from_secs(2));
+            if *reporter_finished.read().expect("could not read finished state") {
+                return;
+            }
+            let counter = reporter_state.lock().expect("could not get state").counter;
+            let attempts = counter - last;
+            last = counter;
+            if attempts == 0 {
+                continue;
+            }
+            let mut stream = reporter_write_stream
+                .lock()
+                .expect("could not lock coordinator stream");
+            if send(&mut stream, &Message::Progress { attempts }).is_err() {
+                return;
+            }
+        }
+    });
+
+    let Some(found) = wait_for_result(&receiver, &finished) else {
+        info!("stopping without a match");
+        return Ok(());
+    };
+
+    info!("found! reporting to coordinator");
+    let mut stream = write_stream
+        .lock()
+        .expect("could not lock coordinator stream");
+    send(
+        &mut stream,
+        &Message::Found {
+            private_blob: found.private_pem,
+            public_blob: found.public_line,
+        },
+    )?;
+
+    Ok(())
+}
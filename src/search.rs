@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use fancy_regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::matcher::Matcher;
+
+/// Every `ssh-ed25519` public key blob starts with this fixed, constant
+/// header (the length-prefixed `"ssh-ed25519"` key type plus the `uint32`
+/// length of the point that follows). `--match prefix` is meaningless
+/// against it, so prefix matching is applied after this offset instead -
+/// that's the point where the key actually starts looking "random" to a
+/// human reading the `ssh-ed25519 AAAA...` line.
+const HEADER_BASE64_LEN: usize = 25;
+
+/// How a generated key's base64 body is compared against `search_terms`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+pub enum MatchMode {
+    /// The term may appear anywhere in the key body.
+    Contains,
+    /// The key body must visually start with the term, right after the
+    /// fixed `ssh-ed25519` header.
+    Prefix,
+    /// The key body must end with the term.
+    Suffix,
+    /// Each term is a regular expression matched against the key body.
+    Regex,
+}
+
+/// The compiled form of `--search` under whichever `--match` mode was
+/// requested.
+pub enum Search {
+    Contains(Matcher),
+    Prefix(Vec<String>),
+    Suffix(Vec<String>),
+    Regex(Vec<Regex>),
+}
+
+impl Search {
+    pub fn new(mode: MatchMode, search_terms: &[String]) -> Result<Self> {
+        Ok(match mode {
+            MatchMode::Contains => Search::Contains(Matcher::new(search_terms)?),
+            MatchMode::Prefix => Search::Prefix(search_terms.to_vec()),
+            MatchMode::Suffix => Search::Suffix(search_terms.to_vec()),
+            MatchMode::Regex => {
+                let regexes = search_terms
+                    .iter()
+                    .map(|term| Regex::new(term).with_context(|| format!("bad regex: {term}")))
+                    .collect::<Result<Vec<_>>>()?;
+                Search::Regex(regexes)
+            }
+        })
+    }
+
+    /// Checks the real (mixed-case) key body against the compiled search
+    /// terms. `contains`/`prefix`/`suffix` are case-folded, matching their
+    /// already-lowercased terms against a lowercased copy of `word`;
+    /// `regex` is matched against `word` as-is, so patterns can use case
+    /// (`[A-Z]`, literals, etc.) to select where letters land.
+    pub fn is_match(&self, word: &str) -> bool {
+        match self {
+            Search::Contains(matcher) => matcher.is_match(&word.to_ascii_lowercase()),
+            Search::Prefix(terms) => {
+                let lower = word.to_ascii_lowercase();
+                let visible = &lower[HEADER_BASE64_LEN.min(lower.len())..];
+                terms.iter().any(|term| visible.starts_with(term))
+            }
+            Search::Suffix(terms) => {
+                let lower = word.to_ascii_lowercase();
+                terms.iter().any(|term| lower.ends_with(term))
+            }
+            Search::Regex(regexes) => regexes
+                .iter()
+                .any(|regex| regex.is_match(word).unwrap_or(false)),
+        }
+    }
+}
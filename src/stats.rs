@@ -0,0 +1,53 @@
+use crate::search::MatchMode;
+
+/// `ssh-ed25519` key bodies are base64 over a 64-symbol alphabet, but
+/// matching is done against a lowercased copy of the body, which folds the
+/// 26 upper/lower pairs of letters together - a letter in a search term is
+/// therefore twice as likely to land on any given position as a digit or
+/// a `+`/`/` symbol.
+const ALPHABET_SIZE: f64 = 64.0;
+
+fn char_hit_probability(c: char) -> f64 {
+    if c.is_ascii_alphabetic() {
+        2.0 / ALPHABET_SIZE
+    } else {
+        1.0 / ALPHABET_SIZE
+    }
+}
+
+fn term_probability(term: &str) -> f64 {
+    term.chars().map(char_hit_probability).product()
+}
+
+/// Rough per-attempt hit probability, summed/union-bounded across terms.
+/// Used only to print an expected-attempts figure and an ETA, so a loose
+/// approximation is fine - there is no general closed form for `regex`
+/// mode, so that mode reports `None`.
+pub fn hit_probability(search_terms: &[String], mode: MatchMode, key_body_len: usize) -> Option<f64> {
+    match mode {
+        MatchMode::Regex => None,
+        MatchMode::Prefix | MatchMode::Suffix => {
+            Some(search_terms.iter().map(|term| term_probability(term)).sum())
+        }
+        MatchMode::Contains => Some(
+            search_terms
+                .iter()
+                .map(|term| {
+                    let positions = key_body_len.saturating_sub(term.chars().count()) + 1;
+                    positions as f64 * term_probability(term)
+                })
+                .sum(),
+        ),
+    }
+}
+
+/// `expected attempts ~= 1 / p`, the usual expectation of a geometric
+/// distribution with success probability `p`.
+pub fn expected_attempts(search_terms: &[String], mode: MatchMode, key_body_len: usize) -> Option<f64> {
+    let p = hit_probability(search_terms, mode, key_body_len)?;
+    if p <= 0.0 {
+        None
+    } else {
+        Some(1.0 / p)
+    }
+}